@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+pub type Key = String;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+#[derive(Clone)]
+pub struct Blackboard {
+    values: HashMap<Key, Value>,
+    versions: HashMap<Key, u64>,
+    clock: u64,
+}
+
+impl Blackboard {
+    pub fn new() -> Self {
+        Blackboard {
+            values: HashMap::new(),
+            versions: HashMap::new(),
+            clock: 0,
+        }
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn set(&mut self, key: Key, value: Value) {
+        self.clock += 1;
+        self.versions.insert(key.clone(), self.clock);
+        self.values.insert(key, value);
+    }
+
+    /// The tick-independent version of `key`: the blackboard-wide write
+    /// clock as of the last `set` of `key`, or 0 if it was never set. A
+    /// cache that remembers the version it last saw can tell whether a key
+    /// changed just by comparing numbers, with no separate dirty flag to
+    /// propagate up front.
+    pub fn version(&self, key: &Key) -> u64 {
+        self.versions.get(key).copied().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_missing_key_returns_none() {
+        let blackboard = Blackboard::new();
+        assert_eq!(blackboard.get(&"speed".to_string()), None);
+    }
+
+    #[test]
+    fn test_set_then_get_returns_value() {
+        let mut blackboard = Blackboard::new();
+        blackboard.set("speed".to_string(), Value::Int(3));
+        assert_eq!(blackboard.get(&"speed".to_string()), Some(&Value::Int(3)));
+    }
+
+    #[test]
+    fn test_version_of_an_unset_key_is_zero() {
+        let blackboard = Blackboard::new();
+        assert_eq!(blackboard.version(&"speed".to_string()), 0);
+    }
+
+    #[test]
+    fn test_set_bumps_the_key_version() {
+        let mut blackboard = Blackboard::new();
+        let before = blackboard.version(&"speed".to_string());
+        blackboard.set("speed".to_string(), Value::Int(3));
+        assert!(blackboard.version(&"speed".to_string()) > before);
+    }
+}
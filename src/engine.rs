@@ -0,0 +1,730 @@
+use crate::blackboard::Blackboard;
+use crate::node::{execute_action_node, execute_condition_node, ActionMap, ConditionMap, Status};
+use crate::tree::{NodeId, NodeType, Tree};
+
+/// A point-in-time copy of an `Engine`'s per-node state and the `Blackboard`
+/// it ticked against, suitable for speculatively ticking a subtree and
+/// discarding the result.
+pub struct Snapshot {
+    states: Vec<Status>,
+    cursors: Vec<usize>,
+    blackboard: Blackboard,
+}
+
+/// Drives a `Tree` with an explicit work stack instead of recursion, so a
+/// `Running` node's progress survives across whole-tree ticks.
+pub struct Engine {
+    states: Vec<Status>,
+    // Per-node progress counter: the index of the child to resume at for a
+    // `Sequence` or `Fallback`, the completed-run count for a `Repeater`, and
+    // the failed-attempt count for a `RetryUntilSuccess`. Unused for other
+    // node types.
+    cursors: Vec<usize>,
+}
+
+enum Work {
+    Visit(NodeId),
+    Collect(NodeId),
+}
+
+impl Engine {
+    pub fn new(tree: &Tree) -> Self {
+        Engine {
+            states: vec![Status::Failure; tree.len()],
+            cursors: vec![0; tree.len()],
+        }
+    }
+
+    pub fn snapshot(&self, blackboard: &Blackboard) -> Snapshot {
+        Snapshot {
+            states: self.states.clone(),
+            cursors: self.cursors.clone(),
+            blackboard: blackboard.clone(),
+        }
+    }
+
+    pub fn rollback(
+        &mut self,
+        snapshot: Snapshot,
+        blackboard: &mut Blackboard,
+        condition_map: &mut ConditionMap,
+    ) {
+        self.states = snapshot.states;
+        self.cursors = snapshot.cursors;
+        *blackboard = snapshot.blackboard;
+        // The restored blackboard's write-versions aren't rolled back to
+        // unique numbers the discarded tick never saw, so a condition's
+        // cached (status, version) pair could otherwise match a later real
+        // write that lands on the same version by coincidence.
+        condition_map.clear_cache();
+    }
+
+    pub fn tick(
+        &mut self,
+        tree: &Tree,
+        root: NodeId,
+        blackboard: &mut Blackboard,
+        condition_map: &mut ConditionMap,
+        action_map: &mut ActionMap,
+    ) -> Status {
+        let mut stack = vec![Work::Visit(root)];
+        while let Some(work) = stack.pop() {
+            match work {
+                Work::Visit(node_id) => match tree.node_type(node_id) {
+                    NodeType::Sequence => {
+                        match tree.children(node_id).get(self.cursors[node_id]) {
+                            Some(&child_id) => {
+                                stack.push(Work::Collect(node_id));
+                                stack.push(Work::Visit(child_id));
+                            }
+                            // A childless Sequence vacuously succeeds, matching
+                            // the original recursive `execute_sequence_node`.
+                            None => self.states[node_id] = Status::Success,
+                        }
+                    }
+                    NodeType::Fallback => {
+                        match tree.children(node_id).get(self.cursors[node_id]) {
+                            Some(&child_id) => {
+                                stack.push(Work::Collect(node_id));
+                                stack.push(Work::Visit(child_id));
+                            }
+                            // A childless Fallback has nothing that can
+                            // succeed.
+                            None => self.states[node_id] = Status::Failure,
+                        }
+                    }
+                    NodeType::Parallel { .. } => {
+                        stack.push(Work::Collect(node_id));
+                        for &child_id in tree.children(node_id).iter().rev() {
+                            stack.push(Work::Visit(child_id));
+                        }
+                    }
+                    NodeType::Inverter | NodeType::Repeater(_) | NodeType::RetryUntilSuccess(_) => {
+                        match tree.children(node_id).first() {
+                            Some(&child_id) => {
+                                stack.push(Work::Collect(node_id));
+                                stack.push(Work::Visit(child_id));
+                            }
+                            // A decorator with no child has nothing to run.
+                            None => self.states[node_id] = Status::Failure,
+                        }
+                    }
+                    NodeType::Condition => {
+                        let status = execute_condition_node(node_id, blackboard, condition_map);
+                        self.states[node_id] = status;
+                    }
+                    NodeType::Action => {
+                        let status = execute_action_node(node_id, blackboard, action_map);
+                        self.states[node_id] = status;
+                    }
+                },
+                Work::Collect(node_id) => {
+                    self.states[node_id] = match tree.node_type(node_id) {
+                        NodeType::Sequence => {
+                            self.collect_sequence(tree, node_id, &mut stack, Status::Success)
+                        }
+                        NodeType::Fallback => {
+                            self.collect_sequence(tree, node_id, &mut stack, Status::Failure)
+                        }
+                        NodeType::Parallel { success_threshold } => {
+                            self.collect_parallel(tree, node_id, *success_threshold)
+                        }
+                        NodeType::Inverter => invert(self.states[tree.children(node_id)[0]]),
+                        NodeType::Repeater(n) => self.collect_repeater(tree, node_id, *n),
+                        NodeType::RetryUntilSuccess(n) => self.collect_retry(tree, node_id, *n),
+                        NodeType::Condition | NodeType::Action => {
+                            unreachable!("leaf nodes report their status directly when visited")
+                        }
+                    };
+                }
+            }
+        }
+
+        self.states[root]
+    }
+
+    /// Shared resume-at-running-child logic for `Sequence` and `Fallback`.
+    /// `advance_on` is the status that makes the walk move on to the next
+    /// child; any other non-`Running` status ends it early.
+    fn collect_sequence(
+        &mut self,
+        tree: &Tree,
+        node_id: NodeId,
+        stack: &mut Vec<Work>,
+        advance_on: Status,
+    ) -> Status {
+        let children = tree.children(node_id);
+        let index = self.cursors[node_id];
+        let child_status = self.states[children[index]];
+
+        if child_status == Status::Running {
+            return Status::Running;
+        }
+        if child_status != advance_on {
+            self.cursors[node_id] = 0;
+            return child_status;
+        }
+        if index + 1 < children.len() {
+            self.cursors[node_id] = index + 1;
+            stack.push(Work::Collect(node_id));
+            stack.push(Work::Visit(children[index + 1]));
+            // Overwritten once the newly pushed `Collect` resolves; the node
+            // isn't actually done yet.
+            return Status::Running;
+        }
+        self.cursors[node_id] = 0;
+        child_status
+    }
+
+    fn collect_parallel(&self, tree: &Tree, node_id: NodeId, success_threshold: usize) -> Status {
+        let children = tree.children(node_id);
+        let successes = children
+            .iter()
+            .filter(|&&id| self.states[id] == Status::Success)
+            .count();
+        let failures = children
+            .iter()
+            .filter(|&&id| self.states[id] == Status::Failure)
+            .count();
+
+        if successes >= success_threshold {
+            Status::Success
+        } else if children.len() - failures < success_threshold {
+            // Not enough children remain that could still succeed.
+            Status::Failure
+        } else {
+            Status::Running
+        }
+    }
+
+    fn collect_repeater(&mut self, tree: &Tree, node_id: NodeId, n: usize) -> Status {
+        let child_status = self.states[tree.children(node_id)[0]];
+        if child_status == Status::Running {
+            return Status::Running;
+        }
+        self.cursors[node_id] += 1;
+        if self.cursors[node_id] < n {
+            Status::Running
+        } else {
+            self.cursors[node_id] = 0;
+            Status::Success
+        }
+    }
+
+    fn collect_retry(&mut self, tree: &Tree, node_id: NodeId, n: usize) -> Status {
+        let child_status = self.states[tree.children(node_id)[0]];
+        match child_status {
+            Status::Running => Status::Running,
+            Status::Success => {
+                self.cursors[node_id] = 0;
+                Status::Success
+            }
+            Status::Failure => {
+                self.cursors[node_id] += 1;
+                if self.cursors[node_id] < n {
+                    Status::Running
+                } else {
+                    self.cursors[node_id] = 0;
+                    Status::Failure
+                }
+            }
+        }
+    }
+}
+
+fn invert(status: Status) -> Status {
+    match status {
+        Status::Success => Status::Failure,
+        Status::Failure => Status::Success,
+        Status::Running => Status::Running,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blackboard::Value;
+    use crate::node::{Action, Condition};
+
+    #[test]
+    fn test_condition_node_returning_true() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(root, Condition::new(|_b| true));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Success);
+    }
+
+    #[test]
+    fn test_sequence_node_with_two_children() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+        let first = tree.add_child(root, NodeType::Condition);
+        let second = tree.add_child(root, NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(first, Condition::new(|_b| true));
+        condition_map.add_condition(second, Condition::new(|_b| false));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Failure);
+    }
+
+    #[test]
+    fn test_sequence_resumes_at_the_running_child_on_the_next_tick() {
+        use std::cell::Cell;
+
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+        let first = tree.add_child(root, NodeType::Action);
+        let second = tree.add_child(root, NodeType::Action);
+
+        // Declared before `action_map` so it outlives the closure that
+        // borrows it (locals drop in reverse declaration order).
+        let first_calls = Cell::new(0);
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+
+        action_map.add_action(
+            first,
+            Action::new(|_b| {
+                first_calls.set(first_calls.get() + 1);
+                Status::Success
+            }),
+        );
+
+        let mut ticked = false;
+        action_map.add_action(
+            second,
+            Action::new(move |_b| {
+                if ticked {
+                    Status::Success
+                } else {
+                    ticked = true;
+                    Status::Running
+                }
+            }),
+        );
+
+        let mut engine = Engine::new(&tree);
+        let first_status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(first_status, Status::Running);
+        assert_eq!(first_calls.get(), 1);
+
+        let second_status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(second_status, Status::Success);
+        // The already-succeeded first child must not be re-run just because
+        // the sequence resumed at its second child.
+        assert_eq!(first_calls.get(), 1);
+    }
+
+    #[test]
+    fn test_rollback_discards_progress_made_since_the_snapshot() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+        let first = tree.add_child(root, NodeType::Action);
+        tree.add_child(root, NodeType::Action);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        action_map.add_action(first, Action::new(|_b| Status::Running));
+
+        let mut engine = Engine::new(&tree);
+        let snapshot = engine.snapshot(&blackboard);
+        engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(engine.states[root], Status::Running);
+
+        engine.rollback(snapshot, &mut blackboard, &mut condition_map);
+        assert_eq!(engine.states[root], Status::Failure);
+    }
+
+    #[test]
+    fn test_rollback_also_restores_the_blackboard() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Action);
+
+        let mut blackboard = Blackboard::new();
+        blackboard.set("speed".to_string(), Value::Int(1));
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        action_map.add_action(
+            root,
+            Action::new(|b| {
+                b.set("speed".to_string(), Value::Int(2));
+                Status::Success
+            }),
+        );
+
+        let mut engine = Engine::new(&tree);
+        let snapshot = engine.snapshot(&blackboard);
+        engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(blackboard.get(&"speed".to_string()), Some(&Value::Int(2)));
+
+        engine.rollback(snapshot, &mut blackboard, &mut condition_map);
+        assert_eq!(blackboard.get(&"speed".to_string()), Some(&Value::Int(1)));
+    }
+
+    #[test]
+    fn test_rollback_invalidates_the_condition_cache() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(
+            root,
+            Condition::with_reads(
+                |b| matches!(b.get(&"flag".to_string()), Some(Value::Bool(true))),
+                &["flag".to_string()],
+            ),
+        );
+
+        let mut engine = Engine::new(&tree);
+        let snapshot = engine.snapshot(&blackboard);
+
+        blackboard.set("flag".to_string(), Value::Bool(true));
+        let speculative = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(speculative, Status::Success);
+
+        engine.rollback(snapshot, &mut blackboard, &mut condition_map);
+
+        // A real write that happens to land on the same blackboard version
+        // the discarded speculative write used must still be re-evaluated,
+        // not answered from the stale cache.
+        blackboard.set("flag".to_string(), Value::Bool(false));
+        let real = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(real, Status::Failure);
+    }
+
+    #[test]
+    fn test_fallback_succeeds_on_first_succeeding_child() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Fallback);
+        let first = tree.add_child(root, NodeType::Condition);
+        let second = tree.add_child(root, NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(first, Condition::new(|_b| false));
+        condition_map.add_condition(second, Condition::new(|_b| true));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Success);
+    }
+
+    #[test]
+    fn test_fallback_fails_when_every_child_fails() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Fallback);
+        let first = tree.add_child(root, NodeType::Condition);
+        let second = tree.add_child(root, NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(first, Condition::new(|_b| false));
+        condition_map.add_condition(second, Condition::new(|_b| false));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Failure);
+    }
+
+    #[test]
+    fn test_sequence_with_no_children_succeeds_vacuously() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Success);
+    }
+
+    #[test]
+    fn test_fallback_with_no_children_fails() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Fallback);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Failure);
+    }
+
+    #[test]
+    fn test_decorator_with_no_child_fails() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Inverter);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Failure);
+    }
+
+    #[test]
+    fn test_parallel_succeeds_once_the_success_threshold_is_met() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Parallel {
+            success_threshold: 2,
+        });
+        let first = tree.add_child(root, NodeType::Condition);
+        let second = tree.add_child(root, NodeType::Condition);
+        let third = tree.add_child(root, NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(first, Condition::new(|_b| true));
+        condition_map.add_condition(second, Condition::new(|_b| true));
+        condition_map.add_condition(third, Condition::new(|_b| false));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Success);
+    }
+
+    #[test]
+    fn test_parallel_fails_once_the_success_threshold_is_unreachable() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Parallel {
+            success_threshold: 2,
+        });
+        let first = tree.add_child(root, NodeType::Condition);
+        let second = tree.add_child(root, NodeType::Condition);
+        let third = tree.add_child(root, NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(first, Condition::new(|_b| true));
+        condition_map.add_condition(second, Condition::new(|_b| false));
+        condition_map.add_condition(third, Condition::new(|_b| false));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Failure);
+    }
+
+    #[test]
+    fn test_inverter_swaps_success_and_failure() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Inverter);
+        let child = tree.add_child(root, NodeType::Condition);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        condition_map.add_condition(child, Condition::new(|_b| true));
+
+        let mut engine = Engine::new(&tree);
+        let status = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+
+        assert_eq!(status, Status::Failure);
+    }
+
+    #[test]
+    fn test_repeater_reruns_its_child_across_ticks_then_succeeds() {
+        use std::cell::Cell;
+
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Repeater(2));
+        let child = tree.add_child(root, NodeType::Action);
+
+        // Declared before `action_map` so it outlives the closure that
+        // borrows it (locals drop in reverse declaration order).
+        let runs = Cell::new(0);
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        action_map.add_action(
+            child,
+            Action::new(|_b| {
+                runs.set(runs.get() + 1);
+                Status::Success
+            }),
+        );
+
+        let mut engine = Engine::new(&tree);
+        let first = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(first, Status::Running);
+        assert_eq!(runs.get(), 1);
+
+        let second = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(second, Status::Success);
+        // The child must have run exactly twice total — once per repeat.
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn test_retry_until_success_gives_up_after_n_failures() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::RetryUntilSuccess(2));
+        let child = tree.add_child(root, NodeType::Action);
+
+        let mut blackboard = Blackboard::new();
+        let mut condition_map = ConditionMap::new();
+        let mut action_map = ActionMap::new();
+        action_map.add_action(child, Action::new(|_b| Status::Failure));
+
+        let mut engine = Engine::new(&tree);
+        let first = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(first, Status::Running);
+
+        let second = engine.tick(
+            &tree,
+            root,
+            &mut blackboard,
+            &mut condition_map,
+            &mut action_map,
+        );
+        assert_eq!(second, Status::Failure);
+    }
+}
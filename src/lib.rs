@@ -0,0 +1,5 @@
+pub mod blackboard;
+pub mod engine;
+pub mod loader;
+pub mod node;
+pub mod tree;
@@ -0,0 +1,348 @@
+use crate::node::{ActionMap, ConditionMap};
+use crate::tree::{NodeId, NodeType, Tree};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BuildError {
+    DuplicateNodeId(u64),
+    UnknownNodeId(u64),
+    OrphanedNodeId(u64),
+    Cycle(u64),
+    InvalidChildCount(u64),
+    MissingConditionHandler(NodeId),
+    MissingActionHandler(NodeId),
+}
+
+#[derive(Deserialize)]
+pub struct TreeDefinition {
+    pub root: u64,
+    pub nodes: Vec<NodeDefinition>,
+}
+
+#[derive(Deserialize)]
+pub struct NodeDefinition {
+    pub id: u64,
+    #[serde(flatten)]
+    pub node_type: NodeTypeDefinition,
+    #[serde(default)]
+    pub children: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+pub enum NodeTypeDefinition {
+    Sequence,
+    Fallback,
+    Parallel { success_threshold: usize },
+    Condition,
+    Action,
+    Inverter,
+    Repeater { n: usize },
+    RetryUntilSuccess { n: usize },
+}
+
+impl From<&NodeTypeDefinition> for NodeType {
+    fn from(def: &NodeTypeDefinition) -> Self {
+        match def {
+            NodeTypeDefinition::Sequence => NodeType::Sequence,
+            NodeTypeDefinition::Fallback => NodeType::Fallback,
+            NodeTypeDefinition::Parallel { success_threshold } => NodeType::Parallel {
+                success_threshold: *success_threshold,
+            },
+            NodeTypeDefinition::Condition => NodeType::Condition,
+            NodeTypeDefinition::Action => NodeType::Action,
+            NodeTypeDefinition::Inverter => NodeType::Inverter,
+            NodeTypeDefinition::Repeater { n } => NodeType::Repeater(*n),
+            NodeTypeDefinition::RetryUntilSuccess { n } => NodeType::RetryUntilSuccess(*n),
+        }
+    }
+}
+
+/// Builds a `Tree` arena from a data-described `TreeDefinition`, decoupling
+/// tree topology (authored/edited externally) from behavior (Rust closures
+/// bound afterwards via `ConditionMap`/`ActionMap`).
+pub struct TreeBuilder;
+
+impl TreeBuilder {
+    pub fn from_definition(definition: &TreeDefinition) -> Result<(Tree, NodeId), BuildError> {
+        let mut by_id = HashMap::new();
+        for node in &definition.nodes {
+            if by_id.insert(node.id, node).is_some() {
+                return Err(BuildError::DuplicateNodeId(node.id));
+            }
+        }
+
+        let root_def = by_id
+            .get(&definition.root)
+            .copied()
+            .ok_or(BuildError::UnknownNodeId(definition.root))?;
+
+        Self::check_arity(root_def)?;
+
+        let mut tree = Tree::new();
+        let mut ids = HashMap::new();
+        let root = tree.add_root(NodeType::from(&root_def.node_type));
+        ids.insert(root_def.id, root);
+        let mut path = HashSet::new();
+        path.insert(root_def.id);
+        Self::add_children(&mut tree, &mut ids, &by_id, root_def, &mut path)?;
+
+        if let Some(node) = definition.nodes.iter().find(|n| !ids.contains_key(&n.id)) {
+            return Err(BuildError::OrphanedNodeId(node.id));
+        }
+
+        Ok((tree, root))
+    }
+
+    /// `path` holds the ids of `parent_def` and all of its ancestors, so a
+    /// child that reappears there is a cycle rather than a legitimate
+    /// diamond (trees don't allow shared children, but a definition is free
+    /// to describe one).
+    fn add_children(
+        tree: &mut Tree,
+        ids: &mut HashMap<u64, NodeId>,
+        by_id: &HashMap<u64, &NodeDefinition>,
+        parent_def: &NodeDefinition,
+        path: &mut HashSet<u64>,
+    ) -> Result<(), BuildError> {
+        let parent_id = ids[&parent_def.id];
+        for &child_external_id in &parent_def.children {
+            if path.contains(&child_external_id) {
+                return Err(BuildError::Cycle(child_external_id));
+            }
+            let child_def = by_id
+                .get(&child_external_id)
+                .copied()
+                .ok_or(BuildError::UnknownNodeId(child_external_id))?;
+            Self::check_arity(child_def)?;
+            let child_id = tree.add_child(parent_id, NodeType::from(&child_def.node_type));
+            ids.insert(child_def.id, child_id);
+            path.insert(child_external_id);
+            Self::add_children(tree, ids, by_id, child_def, path)?;
+            path.remove(&child_external_id);
+        }
+        Ok(())
+    }
+
+    /// Decorators wrap exactly one child; the engine only ever ticks
+    /// `tree.children(node_id)[0]`, so anything beyond the first would be
+    /// built into the tree yet silently never run.
+    fn check_arity(def: &NodeDefinition) -> Result<(), BuildError> {
+        let requires_one_child = matches!(
+            def.node_type,
+            NodeTypeDefinition::Inverter
+                | NodeTypeDefinition::Repeater { .. }
+                | NodeTypeDefinition::RetryUntilSuccess { .. }
+        );
+        if requires_one_child && def.children.len() != 1 {
+            return Err(BuildError::InvalidChildCount(def.id));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that every `Condition`/`Action` node reachable from `root` has a
+/// bound handler, surfacing a typed error instead of the panic that
+/// `ConditionMap::get_condition`/`ActionMap::get_action` would hit on tick.
+pub fn validate_handlers(
+    tree: &Tree,
+    root: NodeId,
+    condition_map: &ConditionMap,
+    action_map: &ActionMap,
+) -> Result<(), BuildError> {
+    for node_id in tree.iter(root) {
+        match tree.node_type(node_id) {
+            NodeType::Condition if !condition_map.contains(node_id) => {
+                return Err(BuildError::MissingConditionHandler(node_id));
+            }
+            NodeType::Action if !action_map.contains(node_id) => {
+                return Err(BuildError::MissingActionHandler(node_id));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::Condition;
+
+    fn sequence_with_one_condition_child() -> TreeDefinition {
+        TreeDefinition {
+            root: 1,
+            nodes: vec![
+                NodeDefinition {
+                    id: 1,
+                    node_type: NodeTypeDefinition::Sequence,
+                    children: vec![2],
+                },
+                NodeDefinition {
+                    id: 2,
+                    node_type: NodeTypeDefinition::Condition,
+                    children: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_from_definition_builds_the_node_graph() {
+        let (tree, root) = TreeBuilder::from_definition(&sequence_with_one_condition_child())
+            .expect("valid definition");
+
+        assert_eq!(tree.children(root).len(), 1);
+    }
+
+    #[test]
+    fn test_from_definition_rejects_duplicate_ids() {
+        let mut definition = sequence_with_one_condition_child();
+        definition.nodes.push(NodeDefinition {
+            id: 2,
+            node_type: NodeTypeDefinition::Action,
+            children: vec![],
+        });
+
+        let err = TreeBuilder::from_definition(&definition).unwrap_err();
+
+        assert_eq!(err, BuildError::DuplicateNodeId(2));
+    }
+
+    #[test]
+    fn test_from_definition_rejects_unknown_child_id() {
+        let mut definition = sequence_with_one_condition_child();
+        definition.nodes[0].children.push(99);
+
+        let err = TreeBuilder::from_definition(&definition).unwrap_err();
+
+        assert_eq!(err, BuildError::UnknownNodeId(99));
+    }
+
+    #[test]
+    fn test_from_definition_rejects_orphaned_node_id() {
+        let mut definition = sequence_with_one_condition_child();
+        definition.nodes.push(NodeDefinition {
+            id: 3,
+            node_type: NodeTypeDefinition::Action,
+            children: vec![],
+        });
+
+        let err = TreeBuilder::from_definition(&definition).unwrap_err();
+
+        assert_eq!(err, BuildError::OrphanedNodeId(3));
+    }
+
+    #[test]
+    fn test_from_definition_rejects_a_cycle() {
+        let definition = TreeDefinition {
+            root: 1,
+            nodes: vec![
+                NodeDefinition {
+                    id: 1,
+                    node_type: NodeTypeDefinition::Sequence,
+                    children: vec![2],
+                },
+                NodeDefinition {
+                    id: 2,
+                    node_type: NodeTypeDefinition::Sequence,
+                    children: vec![1],
+                },
+            ],
+        };
+
+        let err = TreeBuilder::from_definition(&definition).unwrap_err();
+
+        assert_eq!(err, BuildError::Cycle(1));
+    }
+
+    #[test]
+    fn test_from_definition_rejects_a_decorator_with_more_than_one_child() {
+        let definition = TreeDefinition {
+            root: 1,
+            nodes: vec![
+                NodeDefinition {
+                    id: 1,
+                    node_type: NodeTypeDefinition::Inverter,
+                    children: vec![2, 3],
+                },
+                NodeDefinition {
+                    id: 2,
+                    node_type: NodeTypeDefinition::Condition,
+                    children: vec![],
+                },
+                NodeDefinition {
+                    id: 3,
+                    node_type: NodeTypeDefinition::Condition,
+                    children: vec![],
+                },
+            ],
+        };
+
+        let err = TreeBuilder::from_definition(&definition).unwrap_err();
+
+        assert_eq!(err, BuildError::InvalidChildCount(1));
+    }
+
+    #[test]
+    fn test_from_definition_rejects_a_decorator_with_no_children() {
+        let definition = TreeDefinition {
+            root: 1,
+            nodes: vec![NodeDefinition {
+                id: 1,
+                node_type: NodeTypeDefinition::Repeater { n: 2 },
+                children: vec![],
+            }],
+        };
+
+        let err = TreeBuilder::from_definition(&definition).unwrap_err();
+
+        assert_eq!(err, BuildError::InvalidChildCount(1));
+    }
+
+    #[test]
+    fn test_validate_handlers_reports_the_missing_condition_handler() {
+        let (tree, root) =
+            TreeBuilder::from_definition(&sequence_with_one_condition_child()).unwrap();
+        let condition_map = ConditionMap::new();
+        let action_map = ActionMap::new();
+
+        let err = validate_handlers(&tree, root, &condition_map, &action_map).unwrap_err();
+
+        assert_eq!(err, BuildError::MissingConditionHandler(1));
+    }
+
+    #[test]
+    fn test_validate_handlers_passes_once_every_handler_is_bound() {
+        let (tree, root) =
+            TreeBuilder::from_definition(&sequence_with_one_condition_child()).unwrap();
+        let mut condition_map = ConditionMap::new();
+        let action_map = ActionMap::new();
+        condition_map.add_condition(
+            *tree.children(root).first().unwrap(),
+            Condition::new(|_b| true),
+        );
+
+        assert!(validate_handlers(&tree, root, &condition_map, &action_map).is_ok());
+    }
+
+    #[test]
+    fn test_validate_handlers_reports_missing_action_handler() {
+        let definition = TreeDefinition {
+            root: 1,
+            nodes: vec![NodeDefinition {
+                id: 1,
+                node_type: NodeTypeDefinition::Action,
+                children: vec![],
+            }],
+        };
+        let (tree, root) = TreeBuilder::from_definition(&definition).unwrap();
+        let condition_map = ConditionMap::new();
+        let action_map = ActionMap::new();
+
+        let err = validate_handlers(&tree, root, &condition_map, &action_map).unwrap_err();
+
+        assert_eq!(err, BuildError::MissingActionHandler(root));
+    }
+}
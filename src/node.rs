@@ -1,42 +1,32 @@
-use crate::blackboard::Blackboard;
+use crate::blackboard::{Blackboard, Key};
+use crate::tree::NodeId;
 use std::collections::HashMap;
 
-#[derive(Debug, PartialEq)]
-enum Status {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Status {
     Failure,
     Success,
     Running,
 }
 
-enum NodeType {
-    Sequence,
-    Condition,
-    Action,
-}
-
-struct Node {
-    id: u64,
-    node_type: NodeType,
-    children: Vec<Node>,
-}
-
-impl Node {
-    fn new(id: u64, node_type: NodeType) -> Self {
-        Node {
-            id,
-            node_type,
-            children: Vec::new(),
-        }
-    }
-}
-
 pub struct Condition<'a> {
     cb: Box<dyn Fn(&Blackboard) -> bool + 'a>,
+    reads: Vec<Key>,
 }
 
 impl<'a> Condition<'a> {
     pub fn new(cb: impl Fn(&Blackboard) -> bool + 'a) -> Self {
-        Condition { cb: Box::new(cb) }
+        Condition {
+            cb: Box::new(cb),
+            reads: Vec::new(),
+        }
+    }
+
+    pub fn with_reads(cb: impl Fn(&Blackboard) -> bool + 'a, reads: &[Key]) -> Self {
+        Condition {
+            cb: Box::new(cb),
+            reads: reads.to_vec(),
+        }
     }
 
     pub fn evaluate(&self, blackboard: &Blackboard) -> bool {
@@ -44,24 +34,71 @@ impl<'a> Condition<'a> {
     }
 }
 
+struct ConditionCache {
+    status: Status,
+    // The blackboard version of each of the condition's `reads`, in order, as
+    // of the last evaluation. Still matching the blackboard's current
+    // versions means nothing the condition reads has changed since.
+    versions: Vec<u64>,
+}
+
 pub struct ConditionMap<'a> {
-    conditions: HashMap<u64, Condition<'a>>,
+    conditions: HashMap<NodeId, Condition<'a>>,
+    cache: HashMap<NodeId, ConditionCache>,
 }
 
 impl<'a> ConditionMap<'a> {
     pub fn new() -> Self {
         Self {
             conditions: HashMap::new(),
+            cache: HashMap::new(),
         }
     }
 
-    pub fn add_condition(&mut self, node_id: u64, condition: Condition<'a>) {
+    pub fn add_condition(&mut self, node_id: NodeId, condition: Condition<'a>) {
         self.conditions.insert(node_id, condition);
     }
 
-    pub fn get_condition(&self, node_id: u64) -> &Condition<'a> {
+    pub fn get_condition(&self, node_id: NodeId) -> &Condition<'a> {
         self.conditions.get(&node_id).unwrap()
     }
+
+    pub fn contains(&self, node_id: NodeId) -> bool {
+        self.conditions.contains_key(&node_id)
+    }
+
+    /// Drops every cached status. Needed after an `Engine::rollback`: the
+    /// blackboard's write-versions are restored along with its values, so a
+    /// subsequent real write can reuse a version number a discarded
+    /// speculative tick already cached a result against.
+    pub(crate) fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    fn tracks_dependencies(&self, node_id: NodeId) -> bool {
+        !self.get_condition(node_id).reads.is_empty()
+    }
+
+    fn status(&self, node_id: NodeId, blackboard: &Blackboard) -> Option<Status> {
+        let entry = self.cache.get(&node_id)?;
+        let reads = &self.get_condition(node_id).reads;
+        let fresh = reads
+            .iter()
+            .zip(&entry.versions)
+            .all(|(key, &version)| blackboard.version(key) == version);
+        fresh.then_some(entry.status)
+    }
+
+    fn store(&mut self, node_id: NodeId, status: Status, blackboard: &Blackboard) {
+        let versions = self
+            .get_condition(node_id)
+            .reads
+            .iter()
+            .map(|key| blackboard.version(key))
+            .collect();
+        self.cache
+            .insert(node_id, ConditionCache { status, versions });
+    }
 }
 
 pub struct Action<'a> {
@@ -79,7 +116,7 @@ impl<'a> Action<'a> {
 }
 
 pub struct ActionMap<'a> {
-    actions: HashMap<u64, Action<'a>>,
+    actions: HashMap<NodeId, Action<'a>>,
 }
 
 impl<'a> ActionMap<'a> {
@@ -89,71 +126,64 @@ impl<'a> ActionMap<'a> {
         }
     }
 
-    pub fn add_action(&mut self, node_id: u64, action: Action<'a>) {
+    pub fn add_action(&mut self, node_id: NodeId, action: Action<'a>) {
         self.actions.insert(node_id, action);
     }
 
-    pub fn get_action(&mut self, node_id: u64) -> &mut Action<'a> {
+    pub fn get_action(&mut self, node_id: NodeId) -> &mut Action<'a> {
         self.actions.get_mut(&node_id).unwrap()
     }
-}
 
-fn tick(
-    node: &Node,
-    blackboard: &mut Blackboard,
-    condition_map: &ConditionMap,
-    action_map: &mut ActionMap,
-) -> Status {
-    match node.node_type {
-        NodeType::Sequence => execute_sequence_node(node, blackboard, condition_map, action_map),
-        NodeType::Condition => execute_condition_node(node, blackboard, condition_map),
-        NodeType::Action => execute_action_node(node, blackboard, action_map),
+    pub fn contains(&self, node_id: NodeId) -> bool {
+        self.actions.contains_key(&node_id)
     }
 }
 
-fn execute_sequence_node(
-    node: &Node,
-    blackboard: &mut Blackboard,
-    condition_map: &ConditionMap,
-    action_map: &mut ActionMap,
+/// Evaluates a leaf `Condition` node. A condition that declares its reads
+/// consults the version-tagged cache in `ConditionMap` before falling back
+/// to calling the closure; one with no declared reads is not cached and
+/// calls the closure every time.
+pub(crate) fn execute_condition_node(
+    node_id: NodeId,
+    blackboard: &Blackboard,
+    condition_map: &mut ConditionMap,
 ) -> Status {
-    for child_node in node.children.iter() {
-        let status = tick(&child_node, blackboard, condition_map, action_map);
-        if status == Status::Running {
-            return Status::Running;
-        } else if status == Status::Failure {
-            return Status::Failure;
-        }
+    if !condition_map.tracks_dependencies(node_id) {
+        let condition = condition_map.get_condition(node_id);
+        return status_from_bool(condition.evaluate(blackboard));
     }
-    return Status::Success;
+
+    if let Some(cached) = condition_map.status(node_id, blackboard) {
+        return cached;
+    }
+
+    let condition = condition_map.get_condition(node_id);
+    let status = status_from_bool(condition.evaluate(blackboard));
+    condition_map.store(node_id, status, blackboard);
+    status
 }
 
-fn execute_condition_node(
-    node: &Node,
-    blackboard: &Blackboard,
-    condition_map: &ConditionMap,
-) -> Status {
-    let condition = condition_map.get_condition(node.id);
-    if condition.evaluate(blackboard) {
-        return Status::Success;
+fn status_from_bool(result: bool) -> Status {
+    if result {
+        Status::Success
     } else {
-        return Status::Failure;
+        Status::Failure
     }
 }
 
-fn execute_action_node(
-    node: &Node,
+pub(crate) fn execute_action_node(
+    node_id: NodeId,
     blackboard: &mut Blackboard,
     action_map: &mut ActionMap,
 ) -> Status {
-    let action = action_map.get_action(node.id);
+    let action = action_map.get_action(node_id);
     action.execute(blackboard)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::blackboard::Blackboard;
-    use crate::node::{tick, Condition, ConditionMap, Node, NodeType, Status};
+    use crate::blackboard::{Blackboard, Value};
+    use crate::node::{execute_condition_node, Condition, ConditionMap};
 
     #[test]
     fn test_condition() {
@@ -163,65 +193,84 @@ mod tests {
     }
 
     #[test]
-    fn test_condition_node_returning_true() {
-        let node = Node::new(42, NodeType::Condition);
-
-        let blackboard = Blackboard::new();
-        let mut condition_map = ConditionMap::new();
-
-        condition_map.add_condition(node.id, Condition::new(|_b| true));
-
-        let status = tick(&node, &blackboard, &condition_map);
-
-        assert_eq!(status, Status::Success);
-    }
-
-    #[test]
-    fn test_condition_node_returning_false() {
-        let node = Node::new(42, NodeType::Condition);
-
-        let blackboard = Blackboard::new();
+    fn test_condition_with_reads_evaluates_once_before_first_write() {
+        let mut blackboard = Blackboard::new();
         let mut condition_map = ConditionMap::new();
 
-        condition_map.add_condition(node.id, Condition::new(|_b| false));
-
-        let status = tick(&node, &blackboard, &condition_map);
-
-        assert_eq!(status, Status::Failure);
+        condition_map.add_condition(
+            0,
+            Condition::with_reads(
+                |b| matches!(b.get(&"ready".to_string()), Some(Value::Bool(true))),
+                &["ready".to_string()],
+            ),
+        );
+
+        let _ = execute_condition_node(0, &blackboard, &mut condition_map);
+        blackboard.set("ready".to_string(), Value::Bool(true));
+
+        assert!(
+            execute_condition_node(0, &blackboard, &mut condition_map)
+                == crate::node::Status::Success
+        );
     }
 
     #[test]
-    fn test_sequence_node_with_one_child() {
-        let mut parent_node = Node::new(1, NodeType::Sequence);
-        let child_node = Node::new(2, NodeType::Condition);
-
-        let blackboard = Blackboard::new();
+    fn test_condition_with_reads_reflects_a_write_after_an_untouched_evaluation() {
+        let mut blackboard = Blackboard::new();
         let mut condition_map = ConditionMap::new();
-        condition_map.add_condition(child_node.id, Condition::new(|_b| true));
-
-        parent_node.children.push(child_node);
 
-        let status = tick(&parent_node, &blackboard, &condition_map);
-
-        assert_eq!(status, Status::Success);
+        condition_map.add_condition(
+            0,
+            Condition::with_reads(
+                |b| matches!(b.get(&"ready".to_string()), Some(Value::Bool(true))),
+                &["ready".to_string()],
+            ),
+        );
+
+        blackboard.set("ready".to_string(), Value::Bool(false));
+        assert_eq!(
+            execute_condition_node(0, &blackboard, &mut condition_map),
+            crate::node::Status::Failure
+        );
+
+        // Re-evaluating without writing to the blackboard must reuse the
+        // cached result rather than re-running the closure.
+        assert_eq!(
+            execute_condition_node(0, &blackboard, &mut condition_map),
+            crate::node::Status::Failure
+        );
+
+        blackboard.set("ready".to_string(), Value::Bool(true));
+        assert_eq!(
+            execute_condition_node(0, &blackboard, &mut condition_map),
+            crate::node::Status::Success
+        );
     }
 
     #[test]
-    fn test_sequence_node_with_two_children() {
-        let mut parent_node = Node::new(1, NodeType::Sequence);
-        let child_node1 = Node::new(2, NodeType::Condition);
-        let child_node2 = Node::new(3, NodeType::Condition);
+    fn test_condition_with_reads_stays_cached_when_blackboard_is_untouched() {
+        use std::cell::Cell;
 
+        // Declared before `condition_map` so it outlives the boxed closure
+        // that borrows it (locals drop in reverse declaration order).
+        let evaluations = Cell::new(0);
         let blackboard = Blackboard::new();
         let mut condition_map = ConditionMap::new();
-        condition_map.add_condition(child_node1.id, Condition::new(|_b| true));
-        condition_map.add_condition(child_node2.id, Condition::new(|_b| false));
-
-        parent_node.children.push(child_node1);
-        parent_node.children.push(child_node2);
-
-        let status = tick(&parent_node, &blackboard, &condition_map);
 
-        assert_eq!(status, Status::Failure);
+        condition_map.add_condition(
+            0,
+            Condition::with_reads(
+                |_b| {
+                    evaluations.set(evaluations.get() + 1);
+                    true
+                },
+                &["ready".to_string()],
+            ),
+        );
+
+        let _ = execute_condition_node(0, &blackboard, &mut condition_map);
+        let _ = execute_condition_node(0, &blackboard, &mut condition_map);
+
+        assert_eq!(evaluations.get(), 1);
     }
 }
@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+
+pub type NodeId = usize;
+
+#[derive(Debug)]
+pub enum NodeType {
+    Sequence,
+    Fallback,
+    /// Ticks every child each tick and succeeds once `success_threshold` of
+    /// them have succeeded.
+    Parallel {
+        success_threshold: usize,
+    },
+    Condition,
+    Action,
+    /// Wraps a single child and swaps `Success`/`Failure`, passing `Running`
+    /// through unchanged.
+    Inverter,
+    /// Wraps a single child and re-runs it on consecutive ticks until it has
+    /// completed `n` times, then reports `Success`.
+    Repeater(usize),
+    /// Wraps a single child and re-runs it on consecutive ticks after a
+    /// `Failure`, up to `n` attempts, then reports whichever status the
+    /// child last produced.
+    RetryUntilSuccess(usize),
+}
+
+#[derive(Debug)]
+struct NodeSlot {
+    node_type: NodeType,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+#[derive(Debug)]
+pub struct Tree {
+    slots: Vec<NodeSlot>,
+}
+
+impl Tree {
+    pub fn new() -> Self {
+        Tree { slots: Vec::new() }
+    }
+
+    pub fn add_root(&mut self, node_type: NodeType) -> NodeId {
+        let id = self.slots.len();
+        self.slots.push(NodeSlot {
+            node_type,
+            parent: None,
+            children: Vec::new(),
+        });
+        id
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, node_type: NodeType) -> NodeId {
+        let id = self.slots.len();
+        self.slots.push(NodeSlot {
+            node_type,
+            parent: Some(parent),
+            children: Vec::new(),
+        });
+        self.slots[parent].children.push(id);
+        id
+    }
+
+    pub fn node_type(&self, id: NodeId) -> &NodeType {
+        &self.slots[id].node_type
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.slots[id].children
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slots[id].parent
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn iter(&self, root: NodeId) -> NodeIter<'_> {
+        NodeIter::new(self, root)
+    }
+}
+
+/// Breadth-first, non-owning iterator over the ids reachable from `root`.
+pub struct NodeIter<'a> {
+    tree: &'a Tree,
+    queue: VecDeque<NodeId>,
+}
+
+impl<'a> NodeIter<'a> {
+    fn new(tree: &'a Tree, root: NodeId) -> Self {
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+        NodeIter { tree, queue }
+    }
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.queue.pop_front()?;
+        for &child in self.tree.children(id) {
+            self.queue.push_back(child);
+        }
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_root_returns_first_id() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+        assert_eq!(root, 0);
+    }
+
+    #[test]
+    fn test_add_child_links_parent_and_child() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+        let child = tree.add_child(root, NodeType::Condition);
+
+        assert_eq!(tree.children(root), &[child]);
+        assert_eq!(tree.parent(child), Some(root));
+    }
+
+    #[test]
+    fn test_iter_visits_root_then_children_breadth_first() {
+        let mut tree = Tree::new();
+        let root = tree.add_root(NodeType::Sequence);
+        let first = tree.add_child(root, NodeType::Condition);
+        let second = tree.add_child(root, NodeType::Action);
+
+        let visited: Vec<NodeId> = tree.iter(root).collect();
+
+        assert_eq!(visited, vec![root, first, second]);
+    }
+}